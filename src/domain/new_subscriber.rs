@@ -0,0 +1,6 @@
+use super::{SubscriberEmail, SubscriberName};
+
+pub struct NewSubscriber {
+    pub email: SubscriberEmail,
+    pub name: SubscriberName,
+}
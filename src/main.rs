@@ -1,6 +1,8 @@
 use env_logger::Env;
+use secrecy::ExposeSecret;
 use sqlx::PgPool;
 use std::net::TcpListener;
+use zero2prod::email_client::EmailClient;
 use zero2prod::{configuration, startup};
 
 #[tokio::main]
@@ -11,10 +13,30 @@ async fn main() -> Result<(), std::io::Error> {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
     let configuration = configuration::get_configuration().expect("failed to read configuration");
-    let pool = PgPool::connect(&configuration.database.connection_string())
+    let pool = PgPool::connect(configuration.database.connection_string().expose_secret())
         .await
         .expect("failed to connect to postgres");
-    let address = format!("127.0.0.1:{}", configuration.application_port);
+
+    let sender_email = configuration
+        .email_client
+        .sender()
+        .expect("invalid sender email address");
+    let email_client = EmailClient::new(
+        configuration.email_client.base_url,
+        sender_email,
+        configuration.email_client.authorization_token,
+    );
+
+    let address = format!(
+        "{}:{}",
+        configuration.application.host, configuration.application.port
+    );
     let listener = TcpListener::bind(address)?;
-    startup::run(listener, pool)?.await
+    startup::run(
+        listener,
+        pool,
+        email_client,
+        configuration.application.base_url,
+    )?
+    .await
 }
@@ -0,0 +1,7 @@
+mod health_check;
+mod subscriptions;
+mod subscriptions_confirm;
+
+pub use health_check::*;
+pub use subscriptions::*;
+pub use subscriptions_confirm::*;
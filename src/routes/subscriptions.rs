@@ -1,12 +1,12 @@
-use actix_web::{web, HttpResponse, Responder};
+use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName};
+use crate::email_client::EmailClient;
+use crate::startup::ApplicationBaseUrl;
+use actix_web::{web, HttpResponse};
 use chrono::Utc;
-use sqlx::PgPool;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
-// NOTE: `tracing::Instrument` is an extension trait for
-// futures that makes spans interoperate with async code.
-// Anytime a future is polled it enters the corresponding
-// span, when the future is *parked* the span is exited.
-use tracing::Instrument;
 
 #[derive(serde::Deserialize)]
 pub struct FormData {
@@ -14,6 +14,16 @@ pub struct FormData {
     email: String,
 }
 
+impl TryFrom<FormData> for NewSubscriber {
+    type Error = String;
+
+    fn try_from(value: FormData) -> Result<Self, Self::Error> {
+        let name = SubscriberName::parse(value.name)?;
+        let email = SubscriberEmail::parse(value.email)?;
+        Ok(Self { email, name })
+    }
+}
+
 // NOTE: The `web::Data` extractor is used to extract data
 // from the application state. actix-web uses a *type-map*
 // to represent its application-state: A `HashMap` that
@@ -26,55 +36,149 @@ pub struct FormData {
 // NOTE: This technique is similar to what other languages
 // might call *dependency injection*!
 
-pub async fn subscribe(form: web::Form<FormData>, pool: web::Data<PgPool>) -> impl Responder {
-    // NOTE: We *correlate* all logs (traces) related to the
-    // same request using a *request* or *correlation id*.
-    let request_id = Uuid::new_v4();
-    // `tracing::info_span!` creates a span of log-level *info*,
-    // however we still need to explicitly *step into* the span.
-    // Once we do that, all subsequent spans/logs are considered
-    // *children* of this span.
-
-    // NOTE: You can enter/exit spans multiple times, this is handy
-    // for asynchronous tasks for example. Closing is final on the
-    // other hand.
-    let request_span = tracing::info_span!(
-        "adding a new subscriber",
-        // The `tracing` create allows us to associate *structured
-        // information* to spans as key-value pairs. A prefixed `%`
-        // tells `tracing` to use their `Display` trait implementation.
-        %request_id,
+#[tracing::instrument(
+    name = "Adding a new subscriber",
+    skip(form, pool, email_client, base_url),
+    fields(
         subscriber_email = %form.email,
         subscriber_name = %form.name
+    )
+)]
+pub async fn subscribe(
+    form: web::Form<FormData>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    base_url: web::Data<ApplicationBaseUrl>,
+) -> HttpResponse {
+    let new_subscriber = match NewSubscriber::try_from(form.0) {
+        Ok(new_subscriber) => new_subscriber,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(transaction) => transaction,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let subscriber_id = match insert_subscriber(&mut transaction, &new_subscriber).await {
+        Ok(subscriber_id) => subscriber_id,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let subscription_token = generate_subscription_token();
+    if store_token(&mut transaction, subscriber_id, &subscription_token)
+        .await
+        .is_err()
+    {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if transaction.commit().await.is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    if send_confirmation_email(
+        &email_client,
+        new_subscriber,
+        &base_url.0,
+        &subscription_token,
+    )
+    .await
+    .is_err()
+    {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::Ok().finish()
+}
+
+#[tracing::instrument(
+    name = "Sending a confirmation email to a new subscriber",
+    skip(email_client, new_subscriber, base_url, subscription_token)
+)]
+pub async fn send_confirmation_email(
+    email_client: &EmailClient,
+    new_subscriber: NewSubscriber,
+    base_url: &str,
+    subscription_token: &str,
+) -> Result<(), reqwest::Error> {
+    let confirmation_link = format!(
+        "{}/subscriptions/confirm?subscription_token={}",
+        base_url, subscription_token
+    );
+    let html_body = format!(
+        "Welcome to our newsletter!<br />\
+        Click <a href=\"{}\">here</a> to confirm your subscription.",
+        confirmation_link
+    );
+    let plain_body = format!(
+        "Welcome to our newsletter!\nVisit {} to confirm your subscription.",
+        confirmation_link
     );
-    // RAII pattern, the guard is dropped when it's scope ends.
-    let _request_span_guard = request_span.enter();
-    // This span will be *attached* to the future returned by
-    // `sqlx::query!` which is made possible by the `Future`
-    // extension trait `tracing::Instrument`.
-    let query_span = tracing::info_span!("saving new subscriber details in the database",);
-    match sqlx::query!(
+    email_client
+        .send_email(&new_subscriber.email, "Welcome!", &html_body, &plain_body)
+        .await
+}
+
+#[tracing::instrument(
+    name = "Saving new subscriber details in the database",
+    skip(new_subscriber, transaction)
+)]
+pub async fn insert_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    new_subscriber: &NewSubscriber,
+) -> Result<Uuid, sqlx::Error> {
+    let subscriber_id = Uuid::new_v4();
+    sqlx::query!(
         r#"
-        INSERT INTO subscriptions (id, email, name, subscribed_at)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+        VALUES ($1, $2, $3, $4, 'pending_confirmation')
         "#,
-        Uuid::new_v4(),
-        form.email,
-        form.name,
+        subscriber_id,
+        new_subscriber.email.as_ref(),
+        new_subscriber.name.as_ref(),
         Utc::now()
     )
-    // We use `get_ref` to get an immutable ref to `PgPool`
-    // which is wrapped by `web::Data`.
-    .execute(pool.get_ref())
-    // First we attach the instrumentation, then we `await` it.
-    .instrument(query_span)
+    .execute(&mut *transaction)
     .await
-    {
-        Ok(_) => HttpResponse::Ok(),
-        Err(err) => {
-            // TODO: This log falls outside of `query_span` for now.
-            tracing::error!("failed to execute query: {:?}", err);
-            HttpResponse::InternalServerError()
-        }
-    }
+    .map_err(|err| {
+        tracing::error!("failed to execute query: {:?}", err);
+        err
+    })?;
+    Ok(subscriber_id)
+}
+
+#[tracing::instrument(
+    name = "Storing a new subscription token for a new subscriber",
+    skip(subscription_token, transaction)
+)]
+pub async fn store_token(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    subscription_token: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO subscription_tokens (subscription_token, subscriber_id)
+        VALUES ($1, $2)
+        "#,
+        subscription_token,
+        subscriber_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|err| {
+        tracing::error!("failed to execute query: {:?}", err);
+        err
+    })?;
+    Ok(())
+}
+
+/// Generate a random 25-character alphanumeric subscription token.
+fn generate_subscription_token() -> String {
+    let mut rng = thread_rng();
+    std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(25)
+        .collect()
 }
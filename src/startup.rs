@@ -1,21 +1,43 @@
+use crate::email_client::EmailClient;
 use crate::routes;
-use actix_web::{dev::Server, middleware::Logger, web, App, HttpServer};
+use actix_web::{dev::Server, web, App, HttpServer};
 use sqlx::PgPool;
 use std::net::TcpListener;
+use tracing_actix_web::TracingLogger;
 
-pub fn run(listener: TcpListener, pool: PgPool) -> Result<Server, std::io::Error> {
+// `web::Data<String>` would collide with any other `String` wrapped in
+// `web::Data` that might end up in the application state down the line,
+// since actix-web's type-map keys on the `TypeId`. Wrapping it in a
+// dedicated newtype avoids that ambiguity.
+pub struct ApplicationBaseUrl(pub String);
+
+pub fn run(
+    listener: TcpListener,
+    pool: PgPool,
+    email_client: EmailClient,
+    base_url: String,
+) -> Result<Server, std::io::Error> {
     // `web::Data` is used to wrap `pool` in an `Arc`
     // (atomic reference-counter pointer). We need to
     // do so because `pool` can't be shared across
     // threads. Instead we *move* a **clone** of the
     // pointer to the worker.
     let pool = web::Data::new(pool);
+    let email_client = web::Data::new(email_client);
+    let base_url = web::Data::new(ApplicationBaseUrl(base_url));
     let server = HttpServer::new(move || {
         App::new()
-            .wrap(Logger::default()) // Middlewares are added using `wrap`
+            // `TracingLogger` opens a root span for every request,
+            // attaching a generated request id that every log emitted
+            // while handling the request (and all its child spans)
+            // inherits. This replaces `middleware::Logger`.
+            .wrap(TracingLogger::default())
             .route("/health_check", web::get().to(routes::health_check))
             .route("/subscriptions", web::post().to(routes::subscribe))
+            .route("/subscriptions/confirm", web::get().to(routes::confirm))
             .app_data(pool.clone())
+            .app_data(email_client.clone())
+            .app_data(base_url.clone())
         // `app_data` can be used to register information as
         // part of the application state.
     })